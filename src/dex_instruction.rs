@@ -0,0 +1,564 @@
+use std::num::NonZeroU64;
+
+use serum_dex::instruction::{MarketInstruction, SelfTradeBehavior};
+use serum_dex::matching::{OrderType, Side};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar,
+};
+
+use crate::instruction::MarketAccounts;
+
+/// Place a resting or matching limit order directly against the DEX, as
+/// opposed to the atomic market-order semantics of `crate::instruction::swap`.
+#[allow(clippy::too_many_arguments)]
+pub fn new_order_v3(
+    dex_program_id: &Pubkey,
+    market: &MarketAccounts,
+    authority: &Pubkey,
+    side: Side,
+    limit_price: NonZeroU64,
+    max_coin_qty: NonZeroU64,
+    max_native_pc_qty_including_fees: NonZeroU64,
+    self_trade_behavior: SelfTradeBehavior,
+    order_type: OrderType,
+    client_order_id: u64,
+    limit: u16,
+    referral: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*market.market, false),
+        AccountMeta::new(*market.open_orders, false),
+        AccountMeta::new(*market.request_queue, false),
+        AccountMeta::new(*market.event_queue, false),
+        AccountMeta::new(*market.bids, false),
+        AccountMeta::new(*market.asks, false),
+        AccountMeta::new(*market.order_payer_token_account, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*market.coin_vault, false),
+        AccountMeta::new(*market.pc_vault, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+    if let Some(referral) = referral {
+        accounts.push(AccountMeta::new(*referral, false));
+    }
+
+    Instruction {
+        program_id: *dex_program_id,
+        accounts,
+        data: MarketInstruction::NewOrderV3(serum_dex::instruction::NewOrderInstructionV3 {
+            side,
+            limit_price,
+            max_coin_qty,
+            max_native_pc_qty_including_fees,
+            self_trade_behavior,
+            order_type,
+            client_order_id,
+            limit,
+        })
+        .pack(),
+    }
+}
+
+pub fn cancel_order_v2(
+    dex_program_id: &Pubkey,
+    market: &MarketAccounts,
+    authority: &Pubkey,
+    side: Side,
+    order_id: u128,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*market.market, false),
+        AccountMeta::new(*market.bids, false),
+        AccountMeta::new(*market.asks, false),
+        AccountMeta::new(*market.open_orders, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*market.event_queue, false),
+    ];
+
+    Instruction {
+        program_id: *dex_program_id,
+        accounts,
+        data: MarketInstruction::CancelOrderV2(serum_dex::instruction::CancelOrderInstructionV2 { side, order_id })
+            .pack(),
+    }
+}
+
+pub fn cancel_order_by_client_order_id(
+    dex_program_id: &Pubkey,
+    market: &MarketAccounts,
+    authority: &Pubkey,
+    client_order_id: u64,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*market.market, false),
+        AccountMeta::new(*market.bids, false),
+        AccountMeta::new(*market.asks, false),
+        AccountMeta::new(*market.open_orders, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*market.event_queue, false),
+    ];
+
+    Instruction {
+        program_id: *dex_program_id,
+        accounts,
+        data: MarketInstruction::CancelOrderByClientIdV2(client_order_id).pack(),
+    }
+}
+
+pub fn settle_funds(
+    dex_program_id: &Pubkey,
+    market: &MarketAccounts,
+    authority: &Pubkey,
+    pc_wallet: &Pubkey,
+    referral: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*market.market, false),
+        AccountMeta::new(*market.open_orders, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*market.coin_vault, false),
+        AccountMeta::new(*market.pc_vault, false),
+        AccountMeta::new(*market.coin_wallet, false),
+        AccountMeta::new(*pc_wallet, false),
+        AccountMeta::new_readonly(*market.vault_signer, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some(referral) = referral {
+        accounts.push(AccountMeta::new(*referral, false));
+    }
+
+    Instruction { program_id: *dex_program_id, accounts, data: MarketInstruction::SettleFunds.pack() }
+}
+
+/// Immediate-or-cancel take against the book: matches the incoming order,
+/// credits fills straight to the caller's wallets, and returns any unmatched
+/// remainder rather than resting it. Lighter-weight than `swap` for takers who
+/// don't want to pay open-orders rent or do a separate `settle_funds`.
+#[allow(clippy::too_many_arguments)]
+pub fn send_take(
+    dex_program_id: &Pubkey,
+    market: &MarketAccounts,
+    authority: &Pubkey,
+    pc_wallet: &Pubkey,
+    side: Side,
+    limit_price: NonZeroU64,
+    max_coin_qty: NonZeroU64,
+    max_native_pc_qty_including_fees: NonZeroU64,
+    min_coin_qty: u64,
+    min_native_pc_qty: u64,
+    limit: u16,
+    referral: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*market.market, false),
+        AccountMeta::new(*market.request_queue, false),
+        AccountMeta::new(*market.event_queue, false),
+        AccountMeta::new(*market.bids, false),
+        AccountMeta::new(*market.asks, false),
+        AccountMeta::new(*market.coin_wallet, false),
+        AccountMeta::new(*pc_wallet, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*market.coin_vault, false),
+        AccountMeta::new(*market.pc_vault, false),
+        AccountMeta::new_readonly(*market.vault_signer, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some(referral) = referral {
+        accounts.push(AccountMeta::new(*referral, false));
+    }
+
+    Instruction {
+        program_id: *dex_program_id,
+        accounts,
+        data: MarketInstruction::SendTake(serum_dex::instruction::SendTakeInstruction {
+            side,
+            limit_price,
+            max_coin_qty,
+            max_native_pc_qty_including_fees,
+            min_coin_qty,
+            min_native_pc_qty,
+            limit,
+        })
+        .pack(),
+    }
+}
+
+/// Match pending requests from `market`'s request queue against its
+/// bids/asks, up to `limit` matches, pushing fill/out events onto the event queue.
+pub fn match_orders(dex_program_id: &Pubkey, market: &MarketAccounts, limit: u16) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*market.market, false),
+        AccountMeta::new(*market.request_queue, false),
+        AccountMeta::new(*market.event_queue, false),
+        AccountMeta::new(*market.bids, false),
+        AccountMeta::new(*market.asks, false),
+        AccountMeta::new(*market.coin_vault, false),
+        AccountMeta::new(*market.pc_vault, false),
+    ];
+
+    Instruction { program_id: *dex_program_id, accounts, data: MarketInstruction::MatchOrders(limit).pack() }
+}
+
+/// Drain up to `limit` events from `market`'s event queue, crediting the
+/// listed `open_orders` accounts so their balances reflect recent fills.
+pub fn consume_events(
+    dex_program_id: &Pubkey,
+    market: &MarketAccounts,
+    open_orders: &[Pubkey],
+    limit: u16,
+) -> Instruction {
+    let mut accounts: Vec<AccountMeta> = open_orders.iter().map(|key| AccountMeta::new(*key, false)).collect();
+    accounts.extend([
+        AccountMeta::new(*market.market, false),
+        AccountMeta::new(*market.event_queue, false),
+        AccountMeta::new(*market.coin_vault, false),
+        AccountMeta::new(*market.pc_vault, false),
+    ]);
+
+    Instruction { program_id: *dex_program_id, accounts, data: MarketInstruction::ConsumeEvents(limit).pack() }
+}
+
+mod tests {
+    pub use super::*;
+
+    /// Distinct pubkeys per field, so an account-ordering test fails loudly
+    /// on a transposed pair instead of silently passing on a length check.
+    struct DistinctAccounts {
+        market: Pubkey,
+        open_orders: Pubkey,
+        request_queue: Pubkey,
+        event_queue: Pubkey,
+        bids: Pubkey,
+        asks: Pubkey,
+        order_payer_token_account: Pubkey,
+        coin_vault: Pubkey,
+        pc_vault: Pubkey,
+        vault_signer: Pubkey,
+        coin_wallet: Pubkey,
+    }
+
+    impl DistinctAccounts {
+        fn new() -> Self {
+            Self {
+                market: Pubkey::new_unique(),
+                open_orders: Pubkey::new_unique(),
+                request_queue: Pubkey::new_unique(),
+                event_queue: Pubkey::new_unique(),
+                bids: Pubkey::new_unique(),
+                asks: Pubkey::new_unique(),
+                order_payer_token_account: Pubkey::new_unique(),
+                coin_vault: Pubkey::new_unique(),
+                pc_vault: Pubkey::new_unique(),
+                vault_signer: Pubkey::new_unique(),
+                coin_wallet: Pubkey::new_unique(),
+            }
+        }
+
+        fn market_accounts(&self) -> MarketAccounts {
+            MarketAccounts {
+                market: &self.market,
+                open_orders: &self.open_orders,
+                request_queue: &self.request_queue,
+                event_queue: &self.event_queue,
+                bids: &self.bids,
+                asks: &self.asks,
+                order_payer_token_account: &self.order_payer_token_account,
+                coin_vault: &self.coin_vault,
+                pc_vault: &self.pc_vault,
+                vault_signer: &self.vault_signer,
+                coin_wallet: &self.coin_wallet,
+            }
+        }
+    }
+
+    fn account_keys(ix: &Instruction) -> Vec<Pubkey> {
+        ix.accounts.iter().map(|meta| meta.pubkey).collect()
+    }
+
+    #[test]
+    fn new_order_v3_orders_accounts_and_packs_params() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let ix = new_order_v3(
+            &program_id,
+            &market,
+            &authority,
+            Side::Bid,
+            NonZeroU64::new(10).unwrap(),
+            NonZeroU64::new(20).unwrap(),
+            NonZeroU64::new(30).unwrap(),
+            SelfTradeBehavior::DecrementTake,
+            OrderType::Limit,
+            7,
+            65535,
+            None,
+        );
+
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(account_keys(&ix), vec![
+            accounts.market,
+            accounts.open_orders,
+            accounts.request_queue,
+            accounts.event_queue,
+            accounts.bids,
+            accounts.asks,
+            accounts.order_payer_token_account,
+            authority,
+            accounts.coin_vault,
+            accounts.pc_vault,
+            spl_token::id(),
+            sysvar::rent::id(),
+        ]);
+        assert!(ix.accounts[7].is_signer);
+        assert!(!ix.accounts[7].is_writable);
+
+        match MarketInstruction::unpack(&ix.data) {
+            Some(MarketInstruction::NewOrderV3(params)) => {
+                assert_eq!(params.side, Side::Bid);
+                assert_eq!(params.limit_price.get(), 10);
+                assert_eq!(params.max_coin_qty.get(), 20);
+                assert_eq!(params.max_native_pc_qty_including_fees.get(), 30);
+                assert_eq!(params.self_trade_behavior, SelfTradeBehavior::DecrementTake);
+                assert_eq!(params.order_type, OrderType::Limit);
+                assert_eq!(params.client_order_id, 7);
+                assert_eq!(params.limit, 65535);
+            },
+            other => panic!("expected MarketInstruction::NewOrderV3, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn new_order_v3_appends_referral_account() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let pubkey = Pubkey::new_unique();
+        let referral = Pubkey::new_unique();
+        let ix = new_order_v3(
+            &pubkey,
+            &market,
+            &pubkey,
+            Side::Bid,
+            NonZeroU64::new(1).unwrap(),
+            NonZeroU64::new(1).unwrap(),
+            NonZeroU64::new(1).unwrap(),
+            SelfTradeBehavior::DecrementTake,
+            OrderType::Limit,
+            0,
+            65535,
+            Some(&referral),
+        );
+        assert_eq!(ix.accounts.len(), 13);
+        assert_eq!(ix.accounts[12].pubkey, referral);
+    }
+
+    #[test]
+    fn cancel_order_v2_orders_accounts_and_packs_params() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let ix = cancel_order_v2(&program_id, &market, &authority, Side::Ask, 0xDEAD_BEEF);
+
+        assert_eq!(account_keys(&ix), vec![
+            accounts.market,
+            accounts.bids,
+            accounts.asks,
+            accounts.open_orders,
+            authority,
+            accounts.event_queue,
+        ]);
+        assert!(ix.accounts[4].is_signer);
+
+        match MarketInstruction::unpack(&ix.data) {
+            Some(MarketInstruction::CancelOrderV2(params)) => {
+                assert_eq!(params.side, Side::Ask);
+                assert_eq!(params.order_id, 0xDEAD_BEEF);
+            },
+            other => panic!("expected MarketInstruction::CancelOrderV2, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cancel_order_by_client_order_id_orders_accounts_and_packs_id() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let ix = cancel_order_by_client_order_id(&program_id, &market, &authority, 99);
+
+        assert_eq!(account_keys(&ix), vec![
+            accounts.market,
+            accounts.bids,
+            accounts.asks,
+            accounts.open_orders,
+            authority,
+            accounts.event_queue,
+        ]);
+
+        match MarketInstruction::unpack(&ix.data) {
+            Some(MarketInstruction::CancelOrderByClientIdV2(client_order_id)) => assert_eq!(client_order_id, 99),
+            other => panic!("expected MarketInstruction::CancelOrderByClientIdV2, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn settle_funds_orders_accounts_without_referral() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let pc_wallet = Pubkey::new_unique();
+        let ix = settle_funds(&program_id, &market, &authority, &pc_wallet, None);
+
+        assert_eq!(account_keys(&ix), vec![
+            accounts.market,
+            accounts.open_orders,
+            authority,
+            accounts.coin_vault,
+            accounts.pc_vault,
+            accounts.coin_wallet,
+            pc_wallet,
+            accounts.vault_signer,
+            spl_token::id(),
+        ]);
+        assert!(matches!(MarketInstruction::unpack(&ix.data), Some(MarketInstruction::SettleFunds)));
+    }
+
+    #[test]
+    fn send_take_orders_accounts_and_packs_params() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let pc_wallet = Pubkey::new_unique();
+        let ix = send_take(
+            &program_id,
+            &market,
+            &authority,
+            &pc_wallet,
+            Side::Ask,
+            NonZeroU64::new(10).unwrap(),
+            NonZeroU64::new(20).unwrap(),
+            NonZeroU64::new(30).unwrap(),
+            5,
+            6,
+            65535,
+            None,
+        );
+
+        assert_eq!(account_keys(&ix), vec![
+            accounts.market,
+            accounts.request_queue,
+            accounts.event_queue,
+            accounts.bids,
+            accounts.asks,
+            accounts.coin_wallet,
+            pc_wallet,
+            authority,
+            accounts.coin_vault,
+            accounts.pc_vault,
+            accounts.vault_signer,
+            spl_token::id(),
+        ]);
+        assert!(ix.accounts[7].is_signer);
+
+        match MarketInstruction::unpack(&ix.data) {
+            Some(MarketInstruction::SendTake(params)) => {
+                assert_eq!(params.side, Side::Ask);
+                assert_eq!(params.limit_price.get(), 10);
+                assert_eq!(params.max_coin_qty.get(), 20);
+                assert_eq!(params.max_native_pc_qty_including_fees.get(), 30);
+                assert_eq!(params.min_coin_qty, 5);
+                assert_eq!(params.min_native_pc_qty, 6);
+                assert_eq!(params.limit, 65535);
+            },
+            other => panic!("expected MarketInstruction::SendTake, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn send_take_appends_referral_account() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let pubkey = Pubkey::new_unique();
+        let referral = Pubkey::new_unique();
+        let ix = send_take(
+            &pubkey,
+            &market,
+            &pubkey,
+            &pubkey,
+            Side::Ask,
+            NonZeroU64::new(1).unwrap(),
+            NonZeroU64::new(1).unwrap(),
+            NonZeroU64::new(1).unwrap(),
+            0,
+            0,
+            65535,
+            Some(&referral),
+        );
+        assert_eq!(ix.accounts.len(), 13);
+        assert_eq!(ix.accounts[12].pubkey, referral);
+    }
+
+    #[test]
+    fn settle_funds_appends_referral_account() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let pc_wallet = Pubkey::new_unique();
+        let referral = Pubkey::new_unique();
+        let ix = settle_funds(&program_id, &market, &authority, &pc_wallet, Some(&referral));
+        assert_eq!(ix.accounts.len(), 10);
+        assert_eq!(ix.accounts[9].pubkey, referral);
+    }
+
+    #[test]
+    fn match_orders_orders_accounts_and_packs_limit() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let program_id = Pubkey::new_unique();
+        let ix = match_orders(&program_id, &market, 65535);
+
+        assert_eq!(account_keys(&ix), vec![
+            accounts.market,
+            accounts.request_queue,
+            accounts.event_queue,
+            accounts.bids,
+            accounts.asks,
+            accounts.coin_vault,
+            accounts.pc_vault,
+        ]);
+        match MarketInstruction::unpack(&ix.data) {
+            Some(MarketInstruction::MatchOrders(limit)) => assert_eq!(limit, 65535),
+            other => panic!("expected MarketInstruction::MatchOrders, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn consume_events_lists_open_orders_before_market_accounts_and_packs_limit() {
+        let accounts = DistinctAccounts::new();
+        let market = accounts.market_accounts();
+        let program_id = Pubkey::new_unique();
+        let open_orders = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let ix = consume_events(&program_id, &market, &open_orders, 65535);
+
+        assert_eq!(account_keys(&ix), vec![
+            open_orders[0],
+            open_orders[1],
+            accounts.market,
+            accounts.event_queue,
+            accounts.coin_vault,
+            accounts.pc_vault,
+        ]);
+        match MarketInstruction::unpack(&ix.data) {
+            Some(MarketInstruction::ConsumeEvents(limit)) => assert_eq!(limit, 65535),
+            other => panic!("expected MarketInstruction::ConsumeEvents, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod dex_instruction;
+pub mod instruction;
+pub mod market;
+pub mod open_orders;
+pub mod orderbook;
+pub mod queue;
+pub mod quote;
@@ -41,6 +41,12 @@ pub enum Error {
 
     #[error("Transmute error: {0}")]
     TransmuteOther(String),
+
+    #[error("Slab node index {0} is out of bounds")]
+    SlabNodeIndexOutOfBounds(u32),
+
+    #[error("Queue slot index {0} is out of bounds")]
+    QueueSlotIndexOutOfBounds(u32),
 }
 
 impl<'a, T, G> From<safe_transmute::Error<'a, T, G>> for Error {
@@ -137,7 +143,16 @@ pub fn get_market_keys(client: &RpcClient, dex_program_id: Pubkey, market: Pubke
     market_state.pubkeys(dex_program_id)
 }
 
-fn remove_dex_account_padding(data: &[u8]) -> Result<Cow<[u64]>, Error> {
+/// Reassembles a 128-bit on-chain field stored as `[low, high]` native `u64`s.
+///
+/// Raw structs in this crate split any 128-bit field into a `[u64; 2]` pair
+/// rather than using a native `u128`, since `u128` has 16-byte alignment and
+/// silently inserts padding that doesn't match the on-chain byte layout.
+pub(crate) fn u128_from_parts(parts: [u64; 2]) -> u128 {
+    u128::from(parts[0]) | (u128::from(parts[1]) << 64)
+}
+
+pub(crate) fn remove_dex_account_padding(data: &[u8]) -> Result<Cow<[u64]>, Error> {
     let head = &data[..ACCOUNT_HEAD_PADDING.len()];
     if data.len() < ACCOUNT_HEAD_PADDING.len() + ACCOUNT_TAIL_PADDING.len() {
         return Err(Error::AccountLengthTooSmall(data.len()));
@@ -0,0 +1,122 @@
+use safe_transmute::{transmute_one_pedantic, transmute_to_bytes};
+use serum_dex::state::AccountFlag;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::market::{remove_dex_account_padding, u128_from_parts, Error};
+
+/// A user's open-orders account: resting order ids plus the free/locked
+/// coin and pc balances that have accumulated from fills or cancellations.
+// 128-bit fields are split into `[u64; 2]` (low, high) pairs rather than
+// native `u128`s, since `u128`'s 16-byte alignment would make the compiler
+// insert padding that doesn't match the on-chain byte layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct OpenOrders {
+    account_flags: u64,
+    market: [u64; 4],
+    owner: [u64; 4],
+
+    pub native_coin_free: u64,
+    pub native_coin_total: u64,
+    pub native_pc_free: u64,
+    pub native_pc_total: u64,
+
+    free_slot_bits: [u64; 2],
+    is_bid_bits: [u64; 2],
+    orders: [[u64; 2]; 128],
+    pub client_order_ids: [u64; 128],
+
+    referrer_rebates_accrued: u64,
+}
+
+unsafe impl safe_transmute::TriviallyTransmutable for OpenOrders {}
+
+impl OpenOrders {
+    pub fn deserialize(account_data: &[u8]) -> Result<Self, Error> {
+        let words = remove_dex_account_padding(account_data)?;
+        let bytes = transmute_to_bytes(&words);
+        let open_orders = transmute_one_pedantic::<OpenOrders>(bytes).map_err(|err| err.without_src())?;
+
+        AccountFlag::from_bits(open_orders.account_flags)
+            .filter(|flags| flags.contains(AccountFlag::Initialized | AccountFlag::OpenOrders))
+            .ok_or(Error::TransmuteInvalidValue)?;
+
+        Ok(open_orders)
+    }
+
+    pub fn market(&self) -> Pubkey {
+        Pubkey::new(transmute_to_bytes(&self.market))
+    }
+
+    pub fn owner(&self) -> Pubkey {
+        Pubkey::new(transmute_to_bytes(&self.owner))
+    }
+
+    pub fn free_slot_bits(&self) -> u128 {
+        u128_from_parts(self.free_slot_bits)
+    }
+
+    pub fn is_bid_bits(&self) -> u128 {
+        u128_from_parts(self.is_bid_bits)
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = u128> + '_ {
+        self.orders.iter().map(|&parts| u128_from_parts(parts))
+    }
+
+    /// Coin and pc amounts that are currently free to withdraw via `settle_funds`.
+    pub fn settleable_balances(&self) -> (u64, u64) {
+        (self.native_coin_free, self.native_pc_free)
+    }
+}
+
+fn zeroed_open_orders() -> OpenOrders {
+    // OpenOrders is a plain-old-data repr(C) struct transmuted straight from
+    // account bytes, so an all-zero instance is a valid starting point for tests.
+    unsafe { std::mem::zeroed() }
+}
+
+fn wrap_account_bytes(body: &[u8]) -> Vec<u8> {
+    let mut account_data = serum_dex::state::ACCOUNT_HEAD_PADDING.to_vec();
+    account_data.extend_from_slice(body);
+    account_data.extend_from_slice(serum_dex::state::ACCOUNT_TAIL_PADDING);
+    account_data
+}
+
+mod tests {
+    pub use super::*;
+
+    #[test]
+    fn open_orders_is_exactly_3216_bytes() {
+        assert_eq!(std::mem::size_of::<OpenOrders>(), 3216);
+    }
+
+    #[test]
+    fn deserialize_rejects_account_missing_open_orders_flag() {
+        let mut open_orders = zeroed_open_orders();
+        open_orders.account_flags = AccountFlag::Initialized.bits();
+        let body = transmute_to_bytes(std::slice::from_ref(&open_orders));
+        let account_data = wrap_account_bytes(body);
+
+        assert!(OpenOrders::deserialize(&account_data).is_err());
+    }
+
+    #[test]
+    fn deserialize_parses_a_real_open_orders_account() {
+        let mut open_orders = zeroed_open_orders();
+        open_orders.account_flags = (AccountFlag::Initialized | AccountFlag::OpenOrders).bits();
+        open_orders.native_coin_free = 12;
+        open_orders.native_pc_free = 34;
+        open_orders.free_slot_bits = [u64::MAX, 1];
+        open_orders.is_bid_bits = [0, 1];
+        open_orders.orders[0] = [7, 0];
+        let body = transmute_to_bytes(std::slice::from_ref(&open_orders));
+        let account_data = wrap_account_bytes(body);
+
+        let parsed = OpenOrders::deserialize(&account_data).unwrap();
+        assert_eq!(parsed.settleable_balances(), (12, 34));
+        assert_eq!(parsed.free_slot_bits(), (1u128 << 64) | u128::from(u64::MAX));
+        assert_eq!(parsed.is_bid_bits(), 1u128 << 64);
+        assert_eq!(parsed.orders().next(), Some(7u128));
+    }
+}
@@ -0,0 +1,226 @@
+use std::convert::TryInto;
+
+use safe_transmute::{transmute_one_pedantic, transmute_to_bytes};
+use serum_dex::matching::Side;
+use serum_dex::state::MarketState;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::market::{remove_dex_account_padding, Error};
+
+const SLAB_HEADER_LEN: usize = 20;
+const SLAB_NODE_LEN: usize = 72;
+
+const NODE_TAG_INNER: u32 = 1;
+const NODE_TAG_LEAF: u32 = 2;
+// Tags 0 (uninitialized), 3 (free) and 4 (last free) are never part of a live
+// tree and are skipped by the catch-all arm in `collect_leaves`.
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SlabHeader {
+    bump_index: u32,
+    free_list_len: u32,
+    free_list_head: u32,
+    root_node: u32,
+    leaf_count: u32,
+}
+
+unsafe impl safe_transmute::TriviallyTransmutable for SlabHeader {}
+
+// `key` is split into a `[u64; 2]` (low, high) pair rather than a native
+// `u128`: `u128` has 16-byte alignment, which would make the compiler insert
+// padding that doesn't match the 72-byte on-chain node layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct InnerNode {
+    tag: u32,
+    prefix_len: u32,
+    key: [u64; 2],
+    children: [u32; 2],
+    _padding: [u8; SLAB_NODE_LEN - 4 - 4 - 16 - 8],
+}
+
+unsafe impl safe_transmute::TriviallyTransmutable for InnerNode {}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct LeafNode {
+    tag: u32,
+    owner_slot: u8,
+    fee_tier: u8,
+    _padding: [u8; 2],
+    key: [u64; 2],
+    owner: [u64; 4],
+    quantity: u64,
+    client_order_id: u64,
+}
+
+unsafe impl safe_transmute::TriviallyTransmutable for LeafNode {}
+
+/// A single price level of a deserialized order book side, with native
+/// amounts already scaled by the market's lot sizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    pub price: u64,
+    pub quantity: u64,
+    pub owner: Pubkey,
+    pub client_order_id: u64,
+}
+
+/// A deserialized bids/asks critbit slab, ordered from best to worst.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+impl OrderBook {
+    pub fn deserialize(bids_data: &[u8], asks_data: &[u8], market: &MarketState) -> Result<Self, Error> {
+        let mut bids = deserialize_slab(bids_data, market)?;
+        let mut asks = deserialize_slab(asks_data, market)?;
+        // Bids are ordered best (highest price) first, asks best (lowest price) first.
+        bids.sort_by(|a, b| b.price.cmp(&a.price));
+        asks.sort_by(|a, b| a.price.cmp(&b.price));
+        Ok(Self { bids, asks })
+    }
+
+    pub fn best_bid(&self) -> Option<&Level> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&Level> {
+        self.asks.first()
+    }
+
+    pub fn mid_price(&self) -> Option<u64> {
+        let bid = self.best_bid()?;
+        let ask = self.best_ask()?;
+        Some((bid.price + ask.price) / 2)
+    }
+
+    /// Levels for `side`, ordered from best to worst.
+    pub fn levels(&self, side: Side) -> impl Iterator<Item = &Level> {
+        match side {
+            Side::Bid => self.bids.iter(),
+            Side::Ask => self.asks.iter(),
+        }
+    }
+}
+
+fn deserialize_slab(account_data: &[u8], market: &MarketState) -> Result<Vec<Level>, Error> {
+    let words = remove_dex_account_padding(account_data)?;
+    let bytes = transmute_to_bytes(&words);
+
+    let header = transmute_one_pedantic::<SlabHeader>(&bytes[..SLAB_HEADER_LEN]).map_err(|err| err.without_src())?;
+
+    let mut levels = Vec::with_capacity(header.leaf_count as usize);
+    if header.leaf_count > 0 {
+        collect_leaves(&bytes[SLAB_HEADER_LEN..], header.root_node, market, &mut levels)?;
+    }
+    Ok(levels)
+}
+
+fn collect_leaves(nodes: &[u8], index: u32, market: &MarketState, out: &mut Vec<Level>) -> Result<(), Error> {
+    let offset = index as usize * SLAB_NODE_LEN;
+    let node_bytes = nodes
+        .get(offset..offset + SLAB_NODE_LEN)
+        .ok_or(Error::SlabNodeIndexOutOfBounds(index))?;
+    let tag = u32::from_le_bytes(node_bytes[..4].try_into().unwrap());
+
+    match tag {
+        NODE_TAG_INNER => {
+            let inner = transmute_one_pedantic::<InnerNode>(node_bytes).map_err(|err| err.without_src())?;
+            collect_leaves(nodes, inner.children[0], market, out)?;
+            collect_leaves(nodes, inner.children[1], market, out)?;
+        },
+        NODE_TAG_LEAF => {
+            let leaf = transmute_one_pedantic::<LeafNode>(node_bytes).map_err(|err| err.without_src())?;
+            out.push(leaf_to_level(&leaf, market));
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+fn leaf_to_level(leaf: &LeafNode, market: &MarketState) -> Level {
+    // `key`'s high 64 bits are the price in lots; the low 64 bits are a sequence number.
+    let price_lots = leaf.key[1];
+    let owner_bytes: [u8; 32] = transmute_to_bytes(&leaf.owner).try_into().unwrap();
+    Level {
+        price: price_lots.saturating_mul(market.pc_lot_size) / market.coin_lot_size.max(1),
+        quantity: leaf.quantity.saturating_mul(market.coin_lot_size),
+        owner: Pubkey::new(&owner_bytes),
+        client_order_id: leaf.client_order_id,
+    }
+}
+
+fn zeroed_market_state() -> MarketState {
+    // MarketState is a plain-old-data repr(C) struct (it's transmuted straight
+    // from account bytes in `Market::deserialize`), so an all-zero instance
+    // is a valid value; only the lot sizes matter for this test.
+    let mut market: MarketState = unsafe { std::mem::zeroed() };
+    market.pc_lot_size = 1;
+    market.coin_lot_size = 1;
+    market
+}
+
+#[allow(clippy::too_many_arguments)]
+fn leaf_node_bytes(
+    tag: u32,
+    owner_slot: u8,
+    price_lots: u64,
+    seq_num: u64,
+    owner: [u64; 4],
+    quantity: u64,
+    client_order_id: u64,
+) -> [u8; SLAB_NODE_LEN] {
+    let leaf = LeafNode {
+        tag,
+        owner_slot,
+        fee_tier: 0,
+        _padding: [0; 2],
+        key: [seq_num, price_lots],
+        owner,
+        quantity,
+        client_order_id,
+    };
+    let bytes = transmute_to_bytes(std::slice::from_ref(&leaf));
+    bytes.try_into().unwrap()
+}
+
+mod tests {
+    pub use super::*;
+
+    #[test]
+    fn node_structs_are_exactly_72_bytes() {
+        assert_eq!(std::mem::size_of::<InnerNode>(), SLAB_NODE_LEN);
+        assert_eq!(std::mem::size_of::<LeafNode>(), SLAB_NODE_LEN);
+    }
+
+    #[test]
+    fn leaf_node_round_trips_through_transmute() {
+        let owner = [1u64, 2, 3, 4];
+        let bytes = leaf_node_bytes(NODE_TAG_LEAF, 5, 42, 7, owner, 100, 9001);
+        let leaf = transmute_one_pedantic::<LeafNode>(&bytes).unwrap();
+        assert_eq!(leaf.tag, NODE_TAG_LEAF);
+        assert_eq!(leaf.owner_slot, 5);
+        assert_eq!(leaf.key, [7, 42]);
+        assert_eq!(leaf.quantity, 100);
+        assert_eq!(leaf.client_order_id, 9001);
+    }
+
+    #[test]
+    fn collect_leaves_parses_a_single_leaf_tree() {
+        let market = zeroed_market_state();
+        let owner = [11u64, 22, 33, 44];
+        let node_bytes = leaf_node_bytes(NODE_TAG_LEAF, 0, 500, 1, owner, 10, 77);
+
+        let mut levels = Vec::new();
+        collect_leaves(&node_bytes, 0, &market, &mut levels).unwrap();
+
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].price, 500);
+        assert_eq!(levels[0].quantity, 10);
+        assert_eq!(levels[0].client_order_id, 77);
+    }
+}
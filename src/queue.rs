@@ -0,0 +1,381 @@
+use std::convert::TryInto;
+
+use safe_transmute::{transmute_one_pedantic, transmute_to_bytes};
+use serum_dex::state::AccountFlag;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::market::{remove_dex_account_padding, u128_from_parts, Error};
+
+// Each header field occupies a full 8-byte on-chain slot, not just its
+// logical width: the real layout is four u64s, not a u64 plus three
+// padded-out u32s.
+const QUEUE_HEADER_LEN: usize = 32;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct QueueHeader {
+    account_flags: u64,
+    head: u64,
+    count: u64,
+    seq_num: u64,
+}
+
+unsafe impl safe_transmute::TriviallyTransmutable for QueueHeader {}
+
+const EVENT_LEN: usize = 88;
+
+// `order_id` is split into a `[u64; 2]` (low, high) pair rather than a native
+// `u128`: `u128` has 16-byte alignment, which would make the compiler insert
+// padding that doesn't match the on-chain event layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+struct RawEvent {
+    event_flags: u8,
+    owner_slot: u8,
+    fee_tier: u8,
+    _padding: [u8; 5],
+    native_qty_released: u64,
+    native_qty_paid: u64,
+    native_fee_or_rebate: u64,
+    order_id: [u64; 2],
+    owner: [u64; 4],
+    client_order_id: u64,
+}
+
+unsafe impl safe_transmute::TriviallyTransmutable for RawEvent {}
+
+const EVENT_FLAG_FILL: u8 = 0x1;
+const EVENT_FLAG_OUT: u8 = 0x2;
+
+/// A single fill or cancellation event drained from an `EventQueue`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Fill {
+        owner_slot: u8,
+        fee_tier: u8,
+        native_qty_released: u64,
+        native_qty_paid: u64,
+        owner: Pubkey,
+        order_id: u128,
+        client_order_id: u64,
+    },
+    Out {
+        owner_slot: u8,
+        fee_tier: u8,
+        native_qty_released: u64,
+        owner: Pubkey,
+        order_id: u128,
+        client_order_id: u64,
+    },
+}
+
+/// A deserialized `event_q` account: the append-only ring buffer of fill/out
+/// events produced by matching, drained by `consume_events`.
+#[derive(Debug, Clone)]
+pub struct EventQueue {
+    pub head: u64,
+    pub seq_num: u64,
+    events: Vec<Event>,
+}
+
+impl EventQueue {
+    pub fn deserialize(account_data: &[u8]) -> Result<Self, Error> {
+        let words = remove_dex_account_padding(account_data)?;
+        let bytes = transmute_to_bytes(&words);
+        let header =
+            transmute_one_pedantic::<QueueHeader>(&bytes[..QUEUE_HEADER_LEN]).map_err(|err| err.without_src())?;
+
+        AccountFlag::from_bits(header.account_flags)
+            .filter(|flags| flags.contains(AccountFlag::Initialized | AccountFlag::EventQueue))
+            .ok_or(Error::TransmuteInvalidValue)?;
+
+        let body = &bytes[QUEUE_HEADER_LEN..];
+        let capacity = (body.len() / EVENT_LEN).max(1);
+        let mut events = Vec::with_capacity(header.count as usize);
+        for i in 0..header.count as usize {
+            let slot = (header.head as usize + i) % capacity;
+            let offset = slot * EVENT_LEN;
+            let slot_bytes = body
+                .get(offset..offset + EVENT_LEN)
+                .ok_or(Error::QueueSlotIndexOutOfBounds(slot as u32))?;
+            let raw = transmute_one_pedantic::<RawEvent>(slot_bytes).map_err(|err| err.without_src())?;
+            events.push(raw_event_to_event(&raw));
+        }
+
+        Ok(Self { head: header.head, seq_num: header.seq_num, events })
+    }
+
+    /// Pending events, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+fn raw_event_to_event(raw: &RawEvent) -> Event {
+    let owner_bytes: [u8; 32] = transmute_to_bytes(&raw.owner).try_into().unwrap();
+    let owner = Pubkey::new(&owner_bytes);
+    let order_id = u128_from_parts(raw.order_id);
+    if raw.event_flags & EVENT_FLAG_FILL != 0 {
+        Event::Fill {
+            owner_slot: raw.owner_slot,
+            fee_tier: raw.fee_tier,
+            native_qty_released: raw.native_qty_released,
+            native_qty_paid: raw.native_qty_paid,
+            owner,
+            order_id,
+            client_order_id: raw.client_order_id,
+        }
+    } else {
+        debug_assert_ne!(raw.event_flags & EVENT_FLAG_OUT, 0);
+        Event::Out {
+            owner_slot: raw.owner_slot,
+            fee_tier: raw.fee_tier,
+            native_qty_released: raw.native_qty_released,
+            owner,
+            order_id,
+            client_order_id: raw.client_order_id,
+        }
+    }
+}
+
+const REQUEST_LEN: usize = 80;
+
+// `order_id` is split into a `[u64; 2]` (low, high) pair; see the comment on
+// `RawEvent` above for why a native `u128` doesn't match the on-chain layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+struct RawRequest {
+    request_flags: u8,
+    owner_slot: u8,
+    fee_tier: u8,
+    _padding: [u8; 5],
+    max_coin_qty_or_cancel_id: u64,
+    native_pc_qty_locked: u64,
+    order_id: [u64; 2],
+    owner: [u64; 4],
+    client_order_id: u64,
+}
+
+unsafe impl safe_transmute::TriviallyTransmutable for RawRequest {}
+
+/// A single in-flight request drained from a `RequestQueue` by the matching crank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Request {
+    pub owner_slot: u8,
+    pub fee_tier: u8,
+    pub order_id: u128,
+    pub owner: Pubkey,
+    pub client_order_id: u64,
+}
+
+/// A deserialized `request_q` account: new orders and cancellations waiting
+/// to be matched by `match_orders`.
+#[derive(Debug, Clone)]
+pub struct RequestQueue {
+    pub head: u64,
+    pub seq_num: u64,
+    requests: Vec<Request>,
+}
+
+impl RequestQueue {
+    pub fn deserialize(account_data: &[u8]) -> Result<Self, Error> {
+        let words = remove_dex_account_padding(account_data)?;
+        let bytes = transmute_to_bytes(&words);
+        let header =
+            transmute_one_pedantic::<QueueHeader>(&bytes[..QUEUE_HEADER_LEN]).map_err(|err| err.without_src())?;
+
+        AccountFlag::from_bits(header.account_flags)
+            .filter(|flags| flags.contains(AccountFlag::Initialized | AccountFlag::RequestQueue))
+            .ok_or(Error::TransmuteInvalidValue)?;
+
+        let body = &bytes[QUEUE_HEADER_LEN..];
+        let capacity = (body.len() / REQUEST_LEN).max(1);
+        let mut requests = Vec::with_capacity(header.count as usize);
+        for i in 0..header.count as usize {
+            let slot = (header.head as usize + i) % capacity;
+            let offset = slot * REQUEST_LEN;
+            let slot_bytes = body
+                .get(offset..offset + REQUEST_LEN)
+                .ok_or(Error::QueueSlotIndexOutOfBounds(slot as u32))?;
+            let raw = transmute_one_pedantic::<RawRequest>(slot_bytes).map_err(|err| err.without_src())?;
+            let owner_bytes: [u8; 32] = transmute_to_bytes(&raw.owner).try_into().unwrap();
+            requests.push(Request {
+                owner_slot: raw.owner_slot,
+                fee_tier: raw.fee_tier,
+                order_id: u128_from_parts(raw.order_id),
+                owner: Pubkey::new(&owner_bytes),
+                client_order_id: raw.client_order_id,
+            });
+        }
+
+        Ok(Self { head: header.head, seq_num: header.seq_num, requests })
+    }
+
+    /// Pending requests, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Request> {
+        self.requests.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+fn wrap_account_bytes(body: &[u8]) -> Vec<u8> {
+    let mut account_data = serum_dex::state::ACCOUNT_HEAD_PADDING.to_vec();
+    account_data.extend_from_slice(body);
+    account_data.extend_from_slice(serum_dex::state::ACCOUNT_TAIL_PADDING);
+    account_data
+}
+
+fn queue_header_bytes(account_flags: u64, count: u64, seq_num: u64) -> [u8; QUEUE_HEADER_LEN] {
+    let header = QueueHeader { account_flags, head: 0, count, seq_num };
+    transmute_to_bytes(std::slice::from_ref(&header)).try_into().unwrap()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn event_bytes(
+    event_flags: u8,
+    owner_slot: u8,
+    fee_tier: u8,
+    native_qty_released: u64,
+    native_qty_paid: u64,
+    order_id: [u64; 2],
+    owner: [u64; 4],
+    client_order_id: u64,
+) -> [u8; EVENT_LEN] {
+    let raw = RawEvent {
+        event_flags,
+        owner_slot,
+        fee_tier,
+        _padding: [0; 5],
+        native_qty_released,
+        native_qty_paid,
+        native_fee_or_rebate: 0,
+        order_id,
+        owner,
+        client_order_id,
+    };
+    transmute_to_bytes(std::slice::from_ref(&raw)).try_into().unwrap()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn request_bytes(
+    request_flags: u8,
+    owner_slot: u8,
+    fee_tier: u8,
+    order_id: [u64; 2],
+    owner: [u64; 4],
+    client_order_id: u64,
+) -> [u8; REQUEST_LEN] {
+    let raw = RawRequest {
+        request_flags,
+        owner_slot,
+        fee_tier,
+        _padding: [0; 5],
+        max_coin_qty_or_cancel_id: 0,
+        native_pc_qty_locked: 0,
+        order_id,
+        owner,
+        client_order_id,
+    };
+    transmute_to_bytes(std::slice::from_ref(&raw)).try_into().unwrap()
+}
+
+mod tests {
+    pub use super::*;
+
+    #[test]
+    fn raw_structs_match_their_declared_stride() {
+        assert_eq!(std::mem::size_of::<RawEvent>(), EVENT_LEN);
+        assert_eq!(std::mem::size_of::<RawRequest>(), REQUEST_LEN);
+    }
+
+    const EVENT_QUEUE_FLAGS: u64 = (AccountFlag::Initialized.bits()) | (AccountFlag::EventQueue.bits());
+    const REQUEST_QUEUE_FLAGS: u64 = (AccountFlag::Initialized.bits()) | (AccountFlag::RequestQueue.bits());
+
+    #[test]
+    fn event_queue_deserializes_a_populated_queue() {
+        let mut body = queue_header_bytes(EVENT_QUEUE_FLAGS, 1, 5).to_vec();
+        body.extend_from_slice(&event_bytes(EVENT_FLAG_FILL, 2, 3, 100, 200, [9, 0], [1, 2, 3, 4], 55));
+        let account_data = wrap_account_bytes(&body);
+
+        let queue = EventQueue::deserialize(&account_data).unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.seq_num, 5);
+        match *queue.iter().next().unwrap() {
+            Event::Fill { native_qty_released, native_qty_paid, order_id, client_order_id, .. } => {
+                assert_eq!(native_qty_released, 100);
+                assert_eq!(native_qty_paid, 200);
+                assert_eq!(order_id, 9);
+                assert_eq!(client_order_id, 55);
+            },
+            other => panic!("expected Event::Fill, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn event_queue_reads_from_head_in_a_two_slot_ring_buffer() {
+        // Two slots, `head == 1`: the single pending event lives in slot 1, not slot 0.
+        let header = QueueHeader { account_flags: EVENT_QUEUE_FLAGS, head: 1, count: 1, seq_num: 0 };
+        let mut body = transmute_to_bytes(std::slice::from_ref(&header)).to_vec();
+        body.extend_from_slice(&event_bytes(0, 0, 0, 0, 0, [0, 0], [0, 0, 0, 0], 0)); // slot 0: stale/empty
+        body.extend_from_slice(&event_bytes(EVENT_FLAG_OUT, 0, 0, 42, 0, [0, 0], [0, 0, 0, 0], 1)); // slot 1: pending
+        let account_data = wrap_account_bytes(&body);
+
+        let queue = EventQueue::deserialize(&account_data).unwrap();
+        match *queue.iter().next().unwrap() {
+            Event::Out { native_qty_released, client_order_id, .. } => {
+                assert_eq!(native_qty_released, 42);
+                assert_eq!(client_order_id, 1);
+            },
+            other => panic!("expected Event::Out, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn event_queue_rejects_account_missing_event_queue_flag() {
+        let mut body = queue_header_bytes(AccountFlag::Initialized.bits(), 1, 0).to_vec();
+        body.extend_from_slice(&event_bytes(EVENT_FLAG_FILL, 0, 0, 0, 0, [0, 0], [0, 0, 0, 0], 0));
+        let account_data = wrap_account_bytes(&body);
+
+        assert!(EventQueue::deserialize(&account_data).is_err());
+    }
+
+    #[test]
+    fn event_queue_rejects_a_count_that_overruns_the_body() {
+        // `count` claims an event but the body behind the header is empty.
+        let body = queue_header_bytes(EVENT_QUEUE_FLAGS, 1, 0).to_vec();
+        let account_data = wrap_account_bytes(&body);
+
+        assert!(matches!(EventQueue::deserialize(&account_data), Err(Error::QueueSlotIndexOutOfBounds(_))));
+    }
+
+    #[test]
+    fn request_queue_deserializes_a_populated_queue() {
+        let mut body = queue_header_bytes(REQUEST_QUEUE_FLAGS, 1, 3).to_vec();
+        body.extend_from_slice(&request_bytes(0, 1, 0, [99, 0], [5, 6, 7, 8], 21));
+        let account_data = wrap_account_bytes(&body);
+
+        let queue = RequestQueue::deserialize(&account_data).unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.seq_num, 3);
+        let request = queue.iter().next().unwrap();
+        assert_eq!(request.order_id, 99);
+        assert_eq!(request.client_order_id, 21);
+    }
+}
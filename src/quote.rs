@@ -0,0 +1,195 @@
+use serum_dex::matching::Side;
+use serum_swap::ExchangeRate;
+use thiserror::Error;
+
+use crate::orderbook::OrderBook;
+
+/// Result of walking the book to fill `amount` of the coin currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quote {
+    /// Volume-weighted average execution price across the levels touched.
+    pub average_price: u64,
+    /// The worst (last) price touched to fill the requested amount.
+    pub worst_price: u64,
+    /// The amount filled; always the full requested `amount`, since a book too
+    /// shallow to fill it yields `QuoteError::InsufficientLiquidity` instead.
+    pub filled_amount: u64,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QuoteError {
+    #[error("order book has no levels on the requested side")]
+    EmptyBook,
+
+    #[error("book depth ({available}) is insufficient to fill the requested amount ({requested})")]
+    InsufficientLiquidity { requested: u64, available: u64 },
+
+    #[error("combining quotes for a transitive swap overflowed a u64 rate")]
+    RateOverflow,
+}
+
+/// Walk `book`'s `side` level-by-level, accumulating fills until `amount` of
+/// the coin currency is consumed.
+///
+/// `amount` is a coin quantity on both sides, matched against each level's
+/// `quantity`: on the ask side it is the coin to buy, not the pc to spend.
+///
+/// `side` here is the side of the *book* to consume, not the taker's own
+/// order side passed to `swap`/`new_order_v3`: consuming the ask side (lowest
+/// price up) fills a buy of the coin currency, and consuming the bid side
+/// (highest price down) fills a sell of the coin currency. Both use the same
+/// `serum_dex::matching::Side` type, so callers wiring a `quote` into a
+/// `swap` for the same trade must pass the *book* side here, which is the
+/// opposite of `swap`'s taker-order `side`.
+pub fn quote(book: &OrderBook, side: Side, amount: u64) -> Result<Quote, QuoteError> {
+    if amount == 0 {
+        return Ok(Quote { average_price: 0, worst_price: 0, filled_amount: 0 });
+    }
+
+    let mut levels = book.levels(side).peekable();
+    if levels.peek().is_none() {
+        return Err(QuoteError::EmptyBook);
+    }
+
+    let mut remaining = amount;
+    let mut weighted_price_sum: u128 = 0;
+    let mut filled_quantity: u64 = 0;
+    let mut worst_price = 0;
+
+    for level in levels {
+        if remaining == 0 {
+            break;
+        }
+        let fill_quantity = level.quantity.min(remaining);
+        weighted_price_sum += u128::from(level.price) * u128::from(fill_quantity);
+        filled_quantity += fill_quantity;
+        remaining -= fill_quantity;
+        worst_price = level.price;
+    }
+
+    if filled_quantity == 0 {
+        return Err(QuoteError::InsufficientLiquidity { requested: amount, available: 0 });
+    }
+    if remaining > 0 {
+        return Err(QuoteError::InsufficientLiquidity { requested: amount, available: filled_quantity });
+    }
+
+    let average_price = (weighted_price_sum / u128::from(filled_quantity)) as u64;
+    Ok(Quote { average_price, worst_price, filled_amount: filled_quantity })
+}
+
+/// Build the `min_exchange_rate` for a `swap` call from a live quote, applying
+/// `slippage_bps` of tolerance to the quote's worst touched price.
+///
+/// `quote` must have been computed against the book side opposite the `swap`
+/// call's own `side` argument — see the side-convention note on [`quote`].
+pub fn min_exchange_rate(
+    quote: &Quote,
+    from_decimals: u8,
+    quote_decimals: u8,
+    strict: bool,
+    slippage_bps: u16,
+) -> ExchangeRate {
+    let rate = apply_slippage(quote.worst_price, slippage_bps);
+    ExchangeRate { rate, from_decimals, quote_decimals, strict }
+}
+
+/// Chain two book walks through the shared pc currency (as `swap_transitive`
+/// does), returning the combined rate an end-to-end swap would achieve.
+///
+/// Returns `QuoteError::RateOverflow` rather than silently clamping if the two
+/// worst prices can't be combined into a `u64` rate: for a slippage-protection
+/// bound, a clamped-but-wrong value is worse than a loud error.
+pub fn min_exchange_rate_transitive(
+    from_quote: &Quote,
+    to_quote: &Quote,
+    from_decimals: u8,
+    quote_decimals: u8,
+    strict: bool,
+    slippage_bps: u16,
+) -> Result<ExchangeRate, QuoteError> {
+    let scale = 10u64.checked_pow(u32::from(quote_decimals)).ok_or(QuoteError::RateOverflow)?;
+    let combined_rate = from_quote
+        .worst_price
+        .checked_mul(to_quote.worst_price)
+        .and_then(|product| product.checked_div(scale))
+        .ok_or(QuoteError::RateOverflow)?;
+    let rate = apply_slippage(combined_rate, slippage_bps);
+    Ok(ExchangeRate { rate, from_decimals, quote_decimals, strict })
+}
+
+fn apply_slippage(price: u64, slippage_bps: u16) -> u64 {
+    let tolerance = (u128::from(price) * u128::from(slippage_bps) / 10_000) as u64;
+    price.saturating_sub(tolerance)
+}
+
+fn level(price: u64, quantity: u64) -> crate::orderbook::Level {
+    crate::orderbook::Level {
+        price,
+        quantity,
+        owner: solana_sdk::pubkey::Pubkey::new_unique(),
+        client_order_id: 0,
+    }
+}
+
+mod tests {
+    pub use super::*;
+
+    #[test]
+    fn quote_walks_the_ask_side_to_fill_a_buy() {
+        let book = OrderBook { bids: vec![], asks: vec![level(10, 5), level(11, 5)] };
+        let result = quote(&book, Side::Ask, 7).unwrap();
+        // 5 @ 10 + 2 @ 11 = 72, / 7 = 10 (integer division).
+        assert_eq!(result.average_price, 72 / 7);
+        assert_eq!(result.worst_price, 11);
+        assert_eq!(result.filled_amount, 7);
+    }
+
+    #[test]
+    fn quote_walks_the_bid_side_to_fill_a_sell() {
+        let book = OrderBook { bids: vec![level(10, 5), level(9, 5)], asks: vec![] };
+        let result = quote(&book, Side::Bid, 7).unwrap();
+        assert_eq!(result.worst_price, 9);
+        assert_eq!(result.filled_amount, 7);
+    }
+
+    #[test]
+    fn quote_of_zero_amount_trivially_succeeds_even_on_an_empty_book() {
+        let book = OrderBook { bids: vec![], asks: vec![] };
+        assert_eq!(
+            quote(&book, Side::Ask, 0),
+            Ok(Quote { average_price: 0, worst_price: 0, filled_amount: 0 })
+        );
+    }
+
+    #[test]
+    fn quote_errors_on_empty_book_side() {
+        let book = OrderBook { bids: vec![], asks: vec![] };
+        assert_eq!(quote(&book, Side::Ask, 1), Err(QuoteError::EmptyBook));
+    }
+
+    #[test]
+    fn quote_errors_when_book_depth_is_insufficient() {
+        let book = OrderBook { bids: vec![], asks: vec![level(10, 5)] };
+        assert_eq!(
+            quote(&book, Side::Ask, 7),
+            Err(QuoteError::InsufficientLiquidity { requested: 7, available: 5 })
+        );
+    }
+
+    #[test]
+    fn min_exchange_rate_transitive_combines_both_legs() {
+        let from_quote = Quote { average_price: 10, worst_price: 10, filled_amount: 1 };
+        let to_quote = Quote { average_price: 20, worst_price: 20, filled_amount: 1 };
+        let rate = min_exchange_rate_transitive(&from_quote, &to_quote, 6, 0, false, 0).unwrap();
+        assert_eq!(rate.rate, 200);
+    }
+
+    #[test]
+    fn min_exchange_rate_transitive_reports_overflow_instead_of_clamping() {
+        let from_quote = Quote { average_price: u64::MAX, worst_price: u64::MAX, filled_amount: 1 };
+        let to_quote = Quote { average_price: 2, worst_price: 2, filled_amount: 1 };
+        let result = min_exchange_rate_transitive(&from_quote, &to_quote, 6, 0, false, 0);
+        assert!(matches!(result, Err(QuoteError::RateOverflow)));
+    }
+}